@@ -1,46 +1,169 @@
 use soroban_sdk::{symbol_short, Address, Env};
 
-use crate::types::Asset;
+use crate::types::{Role, Side};
 
 /// Emit a deposit event
-/// Topics: ("deposit", user)
-/// Data: (asset, amount)
-pub fn emit_deposit(env: &Env, user: &Address, asset: Asset, amount: i128) {
-    let topics = (symbol_short!("deposit"), user.clone());
-    let data = (asset, amount);
+/// Topics: ("deposit", user, asset_id)
+/// Data: (amount, resulting_balance)
+pub fn emit_deposit(env: &Env, user: &Address, asset_id: u32, amount: i128, resulting_balance: i128) {
+    let topics = (symbol_short!("deposit"), user.clone(), asset_id);
+    let data = (amount, resulting_balance);
     env.events().publish(topics, data);
 }
 
 /// Emit a withdraw event
-/// Topics: ("withdraw", nonce)
-/// Data: (user, asset, amount)
-pub fn emit_withdraw(env: &Env, nonce: u64, user: &Address, asset: Asset, amount: i128) {
-    let topics = (symbol_short!("withdraw"), nonce);
-    let data = (user.clone(), asset, amount);
+/// Topics: ("withdraw", user, asset_id)
+/// Data: (amount, resulting_balance, nonce)
+pub fn emit_withdraw(
+    env: &Env,
+    nonce: u64,
+    user: &Address,
+    asset_id: u32,
+    amount: i128,
+    resulting_balance: i128,
+) {
+    let topics = (symbol_short!("withdraw"), user.clone(), asset_id);
+    let data = (amount, resulting_balance, nonce);
     env.events().publish(topics, data);
 }
 
 /// Emit a settle event for a trade
-/// Topics: ("settle", nonce)
-/// Data: (buyer, seller, asset_sold, amount_sold, asset_bought, amount_bought)
+/// Topics: ("settle", buyer, seller)
+/// Data: (asset_sold, amount_sold, asset_bought, amount_bought, nonce)
 pub fn emit_settle(
     env: &Env,
     nonce: u64,
     buyer: &Address,
     seller: &Address,
-    asset_sold: Asset,
+    asset_sold: u32,
     amount_sold: i128,
-    asset_bought: Asset,
+    asset_bought: u32,
     amount_bought: i128,
 ) {
-    let topics = (symbol_short!("settle"), nonce);
+    let topics = (symbol_short!("settle"), buyer.clone(), seller.clone());
+    let data = (asset_sold, amount_sold, asset_bought, amount_bought, nonce);
+    env.events().publish(topics, data);
+}
+
+/// Emit a role_granted event
+/// Topics: ("rolegrant", who)
+/// Data: role
+pub fn emit_role_granted(env: &Env, who: &Address, role: Role) {
+    let topics = (symbol_short!("rolegrant"), who.clone());
+    env.events().publish(topics, role);
+}
+
+/// Emit a role_revoked event
+/// Topics: ("rolerevok", who)
+/// Data: role
+pub fn emit_role_revoked(env: &Env, who: &Address, role: Role) {
+    let topics = (symbol_short!("rolerevok"), who.clone());
+    env.events().publish(topics, role);
+}
+
+/// Emit an emergency_withdraw event
+/// Topics: ("emergency", user)
+/// Data: (asset_id, amount)
+pub fn emit_emergency_withdraw(env: &Env, user: &Address, asset_id: u32, amount: i128) {
+    let topics = (symbol_short!("emergency"), user.clone());
+    let data = (asset_id, amount);
+    env.events().publish(topics, data);
+}
+
+/// Emit a paused event
+/// Topics: ("paused",)
+pub fn emit_paused(env: &Env) {
+    let topics = (symbol_short!("paused"),);
+    env.events().publish(topics, ());
+}
+
+/// Emit an unpaused event
+/// Topics: ("unpaused",)
+pub fn emit_unpaused(env: &Env) {
+    let topics = (symbol_short!("unpaused"),);
+    env.events().publish(topics, ());
+}
+
+/// Emit an upgraded event
+/// Topics: ("upgraded",)
+/// Data: (old_version, new_version)
+pub fn emit_upgraded(env: &Env, old_version: u32, new_version: u32) {
+    let topics = (symbol_short!("upgraded"),);
+    let data = (old_version, new_version);
+    env.events().publish(topics, data);
+}
+
+/// Emit an asset_registered event
+/// Topics: ("assetreg", asset_id)
+/// Data: token
+pub fn emit_asset_registered(env: &Env, asset_id: u32, token: &Address) {
+    let topics = (symbol_short!("assetreg"), asset_id);
+    env.events().publish(topics, token.clone());
+}
+
+/// Emit an order_placed event for a limit order that rested on the book
+/// Topics: ("orderplac", order_id)
+/// Data: (owner, side, price, remaining)
+pub fn emit_order_placed(env: &Env, order_id: u64, owner: &Address, side: Side, price: i128, remaining: i128) {
+    let topics = (symbol_short!("orderplac"), order_id);
+    let data = (owner.clone(), side, price, remaining);
+    env.events().publish(topics, data);
+}
+
+/// Emit an order_cancelled event
+/// Topics: ("ordercanc", order_id)
+/// Data: owner
+pub fn emit_order_cancelled(env: &Env, order_id: u64, owner: &Address) {
+    let topics = (symbol_short!("ordercanc"), order_id);
+    env.events().publish(topics, owner.clone());
+}
+
+/// Emit a sigsettle event for a trade settled from two user-signed orders
+/// Topics: ("sigsettle", buy_order_maker)
+/// Data: (sell_order_maker, asset_sold_by_buy_order, amount_sold_by_buy_order, asset_sold_by_sell_order, amount_sold_by_sell_order)
+pub fn emit_signed_settle(
+    env: &Env,
+    buy_order_maker: &Address,
+    sell_order_maker: &Address,
+    buy_order_asset_sell: u32,
+    buy_order_amount_sell: i128,
+    sell_order_asset_sell: u32,
+    sell_order_amount_sell: i128,
+) {
+    let topics = (symbol_short!("sigsettle"), buy_order_maker.clone());
     let data = (
-        buyer.clone(),
-        seller.clone(),
-        asset_sold,
-        amount_sold,
-        asset_bought,
-        amount_bought,
+        sell_order_maker.clone(),
+        buy_order_asset_sell,
+        buy_order_amount_sell,
+        sell_order_asset_sell,
+        sell_order_amount_sell,
     );
     env.events().publish(topics, data);
 }
+
+/// Emit a fees_withdrawn event
+/// Topics: ("feeswithd", caller)
+/// Data: (asset_id, amount)
+pub fn emit_fees_withdrawn(env: &Env, caller: &Address, asset_id: u32, amount: i128) {
+    let topics = (symbol_short!("feeswithd"), caller.clone());
+    let data = (asset_id, amount);
+    env.events().publish(topics, data);
+}
+
+/// Emit a fill event for a single match between a taker and a resting maker order
+/// Topics: ("fill", maker_order_id)
+/// Data: (buyer, seller, base_asset, quote_asset, amount, price)
+pub fn emit_fill(
+    env: &Env,
+    maker_order_id: u64,
+    buyer: &Address,
+    seller: &Address,
+    base_asset: u32,
+    quote_asset: u32,
+    amount: i128,
+    price: i128,
+) {
+    let topics = (symbol_short!("fill"), maker_order_id);
+    let data = (buyer.clone(), seller.clone(), base_asset, quote_asset, amount, price);
+    env.events().publish(topics, data);
+}
@@ -1,30 +1,168 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, token, Address, Env};
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, Vec};
 
 mod events;
 mod storage;
 mod types;
 
-use types::Asset;
+use types::{Error, Order, Role, Side, SignedOrder, Trade, WithdrawLimit};
+
+/// Current storage schema version. Bumped whenever a new release needs
+/// `migrate` to run a one-time storage change; compared against the
+/// persisted `DataKey::Version` counter to keep `migrate` idempotent.
+const CURRENT_VERSION: u32 = 1;
 
 #[contract]
 pub struct OrderBookContract;
 
 #[contractimpl]
 impl OrderBookContract {
-    /// Constructor: Initialize the order book contract with admin and two token contracts.
+    /// Constructor: Initialize the order book contract with admin and two initial token contracts.
     /// Called automatically during contract deployment.
     ///
+    /// The admin is bootstrapped as the contract's first `RoleAdmin`, so it
+    /// can grant itself (or other operators) `Settler`/`Withdrawer` roles.
+    /// `asset_a` and `asset_b` are registered as asset ids 0 and 1; the
+    /// admin can register further pairs later via `register_asset`.
+    ///
     /// # Arguments
-    /// * `admin` - The admin address that will authorize withdrawals and settlements
-    /// * `asset_a` - Token contract address for asset A
-    /// * `asset_b` - Token contract address for asset B
-    pub fn __constructor(env: Env, admin: Address, asset_a: Address, asset_b: Address) {
+    /// * `admin` - The admin address, bootstrapped as `RoleAdmin`
+    /// * `asset_a` - Token contract address registered as asset id 0
+    /// * `asset_b` - Token contract address registered as asset id 1
+    ///
+    /// # Errors
+    /// Returns `Error::AssetAlreadyRegistered` if `asset_a` and `asset_b`
+    /// are the same token address
+    pub fn __constructor(env: Env, admin: Address, asset_a: Address, asset_b: Address) -> Result<(), Error> {
         storage::set_admin(&env, &admin);
-        storage::set_asset_a(&env, &asset_a);
-        storage::set_asset_b(&env, &asset_b);
+        Self::register_asset_with_decimals(&env, &asset_a)?;
+        Self::register_asset_with_decimals(&env, &asset_b)?;
+        storage::grant_role(&env, &admin, Role::RoleAdmin);
+        storage::set_version(&env, CURRENT_VERSION);
+        storage::extend_instance_ttl(&env);
+
+        Ok(())
+    }
+
+    /// Register `token` and capture its SAC-reported decimals in one step.
+    /// Shared by the constructor's bootstrap registrations and `register_asset`.
+    fn register_asset_with_decimals(env: &Env, token: &Address) -> Result<u32, Error> {
+        let asset_id = storage::register_asset(env, token)?;
+        let decimals = token::Client::new(env, token).decimals();
+        storage::set_asset_decimals(env, asset_id, decimals);
+        Ok(asset_id)
+    }
+
+    /// Register a new tradeable token, assigning it the next available asset id.
+    ///
+    /// Only a caller holding `RoleAdmin` may register assets. Lets one
+    /// deployment serve many trading pairs instead of one per pair.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `token` - The token contract address to register
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`, or
+    /// `Error::AssetAlreadyRegistered` if `token` is already registered
+    ///
+    /// # Returns
+    /// The newly assigned asset id
+    pub fn register_asset(env: Env, caller: Address, token: Address) -> Result<u32, Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        storage::extend_instance_ttl(&env);
+        let asset_id = Self::register_asset_with_decimals(&env, &token)?;
+        events::emit_asset_registered(&env, asset_id, &token);
+
+        Ok(asset_id)
+    }
+
+    /// Check whether an asset id has been registered.
+    ///
+    /// # Arguments
+    /// * `asset_id` - The asset id to query
+    ///
+    /// # Returns
+    /// `true` if `asset_id` is registered
+    pub fn asset_exists(env: Env, asset_id: u32) -> bool {
+        storage::extend_instance_ttl(&env);
+        storage::asset_exists(&env, asset_id)
+    }
+
+    /// Get the decimals reported by a registered asset's token, captured
+    /// from its SAC metadata when it was registered.
+    ///
+    /// # Arguments
+    /// * `asset_id` - Which registered asset to query
+    ///
+    /// # Errors
+    /// Returns `Error::NotInitialized` if `asset_id` isn't registered
+    ///
+    /// # Returns
+    /// The token's decimals
+    pub fn get_decimals(env: Env, asset_id: u32) -> Result<u32, Error> {
         storage::extend_instance_ttl(&env);
+        storage::get_decimals(&env, asset_id)
+    }
+
+    /// Grant an operator role to an address.
+    ///
+    /// Only a caller holding `RoleAdmin` may grant roles.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `who` - The address to grant the role to
+    /// * `role` - The role to grant
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` doesn't hold `RoleAdmin`
+    pub fn grant_role(env: Env, caller: Address, who: Address, role: Role) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        storage::extend_instance_ttl(&env);
+        storage::grant_role(&env, &who, role);
+        events::emit_role_granted(&env, &who, role);
+
+        Ok(())
+    }
+
+    /// Revoke an operator role from an address.
+    ///
+    /// Only a caller holding `RoleAdmin` may revoke roles.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `who` - The address to revoke the role from
+    /// * `role` - The role to revoke
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` doesn't hold `RoleAdmin`
+    pub fn revoke_role(env: Env, caller: Address, who: Address, role: Role) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        storage::extend_instance_ttl(&env);
+        storage::revoke_role(&env, &who, role);
+        events::emit_role_revoked(&env, &who, role);
+
+        Ok(())
+    }
+
+    /// Check whether an address holds a given role.
+    ///
+    /// # Arguments
+    /// * `who` - The address to query
+    /// * `role` - The role to check
+    ///
+    /// # Returns
+    /// `true` if `who` currently holds `role`
+    pub fn has_role(env: Env, who: Address, role: Role) -> bool {
+        storage::extend_instance_ttl(&env);
+        storage::has_role(&env, &who, role)
     }
 
     /// Deposit tokens into the order book.
@@ -34,23 +172,30 @@ impl OrderBookContract {
     ///
     /// # Arguments
     /// * `user` - The user depositing tokens
-    /// * `asset` - Which asset to deposit (A or B)
+    /// * `asset_id` - Which registered asset to deposit
     /// * `amount` - Amount to deposit (must be positive)
     ///
+    /// # Errors
+    /// Returns `Error::ContractPaused` if the contract is paused,
+    /// `Error::NonPositiveAmount` if amount is not positive, or
+    /// `Error::NotInitialized` if `asset_id` isn't registered
+    ///
     /// # Panics
-    /// Panics if amount is not positive or if token transfer fails
-    pub fn deposit(env: Env, user: Address, asset: Asset, amount: i128) {
+    /// Panics if token transfer fails
+    pub fn deposit(env: Env, user: Address, asset_id: u32, amount: i128) -> Result<(), Error> {
         // User must authorize the deposit
         user.require_auth();
 
+        storage::require_not_paused(&env)?;
+
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(Error::NonPositiveAmount);
         }
 
         storage::extend_instance_ttl(&env);
 
         // Get the token contract address
-        let token_address = storage::get_asset_address(&env, asset);
+        let token_address = storage::get_asset_address(&env, asset_id)?;
         let token_client = token::Client::new(&env, &token_address);
 
         // Transfer tokens from user to this contract
@@ -58,45 +203,75 @@ impl OrderBookContract {
         token_client.transfer(&user, &contract_address, &amount);
 
         // Update user's balance
-        storage::increase_balance(&env, &user, asset, amount);
+        storage::increase_balance(&env, &user, asset_id, amount);
 
         // Emit deposit event for the backend to track
-        events::emit_deposit(&env, &user, asset, amount);
+        let resulting_balance = storage::get_user_balance(&env, &user, asset_id);
+        events::emit_deposit(&env, &user, asset_id, amount, resulting_balance);
+
+        Ok(())
     }
 
     /// Withdraw tokens from the order book.
     ///
-    /// Only the admin can authorize withdrawals. The backend checks that the user
-    /// has no outstanding liabilities before requesting a withdrawal.
+    /// Only a caller holding the `Withdrawer` role can authorize withdrawals.
+    /// The backend checks that the user has no outstanding liabilities
+    /// before requesting a withdrawal.
     ///
     /// # Arguments
+    /// * `operator` - Must hold `Withdrawer` and authorize the call
     /// * `nonce` - Execution nonce (must match current contract nonce)
     /// * `user` - The user withdrawing tokens
-    /// * `asset` - Which asset to withdraw (A or B)
+    /// * `asset_id` - Which registered asset to withdraw
     /// * `amount` - Amount to withdraw (must be positive)
     ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `operator` lacks `Withdrawer`,
+    /// `Error::ContractPaused` if the contract is paused,
+    /// `Error::NonPositiveAmount` if amount is not positive,
+    /// `Error::WithdrawLimitExceeded` if `asset_id` has a configured rate
+    /// cap and this withdrawal would exceed it,
+    /// `Error::InsufficientBalance` if the user lacks funds,
+    /// `Error::InvalidNonce` if the nonce doesn't match, or
+    /// `Error::NotInitialized` if `asset_id` isn't registered
+    ///
     /// # Panics
-    /// Panics if amount is not positive, user has insufficient balance,
-    /// nonce doesn't match, or admin doesn't authorize
-    pub fn withdraw(env: Env, nonce: u64, user: Address, asset: Asset, amount: i128) {
-        // Admin must authorize withdrawals
-        let admin = storage::get_admin(&env);
-        admin.require_auth();
+    /// Panics if `operator` doesn't authorize or token transfer fails
+    pub fn withdraw(
+        env: Env,
+        operator: Address,
+        nonce: u64,
+        user: Address,
+        asset_id: u32,
+        amount: i128,
+    ) -> Result<(), Error> {
+        // Operator must hold the Withdrawer role and authorize the call
+        operator.require_auth();
+        storage::require_role(&env, &operator, Role::Withdrawer)?;
+        storage::require_not_paused(&env)?;
 
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(Error::NonPositiveAmount);
         }
 
         storage::extend_instance_ttl(&env);
 
         // Validate nonce matches current value
-        storage::validate_nonce(&env, nonce);
+        storage::validate_nonce(&env, nonce)?;
+
+        // Reject unregistered assets before touching any balance or window
+        let token_address = storage::get_asset_address(&env, asset_id)?;
+
+        // Check (and record against) the asset's rate-limit window, if
+        // configured. No event is emitted on rejection: an `Err` return
+        // reverts the whole invocation, so anything published here would
+        // never actually reach an indexer.
+        storage::check_and_record_withdrawal(&env, asset_id, amount)?;
 
-        // Decrease user's balance (will panic if insufficient)
-        storage::decrease_balance(&env, &user, asset, amount);
+        // Decrease user's balance (fails if insufficient)
+        storage::decrease_balance(&env, &user, asset_id, amount)?;
 
         // Transfer tokens from contract to user
-        let token_address = storage::get_asset_address(&env, asset);
         let token_client = token::Client::new(&env, &token_address);
         let contract_address = env.current_contract_address();
         token_client.transfer(&contract_address, &user, &amount);
@@ -105,64 +280,274 @@ impl OrderBookContract {
         storage::increment_nonce(&env);
 
         // Emit withdraw event
-        events::emit_withdraw(&env, nonce, &user, asset, amount);
+        let resulting_balance = storage::get_user_balance(&env, &user, asset_id);
+        events::emit_withdraw(&env, nonce, &user, asset_id, amount, resulting_balance);
+
+        Ok(())
     }
 
-    /// Settle a trade between two users.
+    /// Reclaim a user's own tracked balance while the contract is paused.
+    ///
+    /// Bypasses the operator nonce/role machinery entirely so users can
+    /// always recover their own funds even if the backend operator is
+    /// unresponsive or compromised. Only callable while paused; once the
+    /// incident is resolved, `withdraw` resumes as the normal path.
+    ///
+    /// # Arguments
+    /// * `user` - The user withdrawing their own tokens; must authorize
+    /// * `asset_id` - Which registered asset to withdraw
+    /// * `amount` - Amount to withdraw (must be positive)
     ///
-    /// Only the admin can authorize settlements. The backend matches orders
-    /// off-chain and submits settlements on-chain.
+    /// # Errors
+    /// Returns `Error::NonPositiveAmount` if amount is not positive,
+    /// `Error::InsufficientBalance` if the user lacks funds, or
+    /// `Error::NotInitialized` if `asset_id` isn't registered. Note this
+    /// call only succeeds while the contract is paused.
+    ///
+    /// # Panics
+    /// Panics if `user` doesn't authorize or token transfer fails
+    pub fn emergency_withdraw(
+        env: Env,
+        user: Address,
+        asset_id: u32,
+        amount: i128,
+    ) -> Result<(), Error> {
+        user.require_auth();
+
+        if !storage::is_paused(&env) {
+            return Err(Error::Unauthorized);
+        }
+
+        if amount <= 0 {
+            return Err(Error::NonPositiveAmount);
+        }
+
+        storage::extend_instance_ttl(&env);
+
+        // Reject unregistered assets before touching any balance
+        let token_address = storage::get_asset_address(&env, asset_id)?;
+
+        // Decrease user's balance (fails if insufficient)
+        storage::decrease_balance(&env, &user, asset_id, amount)?;
+
+        // Transfer tokens from contract to user
+        let token_client = token::Client::new(&env, &token_address);
+        let contract_address = env.current_contract_address();
+        token_client.transfer(&contract_address, &user, &amount);
+
+        events::emit_emergency_withdraw(&env, &user, asset_id, amount);
+
+        Ok(())
+    }
+
+    /// Pause or unpause deposits, withdrawals, and settlement.
+    ///
+    /// While paused, `deposit`, `withdraw`, `settle`, and `settle_batch` all
+    /// revert; users can still reclaim their tracked balance via
+    /// `emergency_withdraw`. Only a caller holding `RoleAdmin` may toggle
+    /// the switch.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `paused` - The new paused state
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`
+    pub fn set_paused(env: Env, caller: Address, paused: bool) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        storage::extend_instance_ttl(&env);
+        storage::set_paused(&env, paused);
+
+        if paused {
+            events::emit_paused(&env);
+        } else {
+            events::emit_unpaused(&env);
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the contract is currently paused.
+    ///
+    /// # Returns
+    /// `true` if deposits, withdrawals, and settlement are currently frozen
+    pub fn is_paused(env: Env) -> bool {
+        storage::extend_instance_ttl(&env);
+        storage::is_paused(&env)
+    }
+
+    /// Configure (or clear) a rolling-window withdrawal rate cap for an asset.
+    ///
+    /// Bounds outflow of a single asset to `limit` (in the token's own
+    /// smallest unit) per `window_ledgers` ledgers, to contain damage from
+    /// a compromised `Withdrawer` key. Only a caller holding `RoleAdmin`
+    /// may configure limits.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `asset_id` - Which registered asset to cap
+    /// * `limit` - Max total withdrawn per window, in the asset's smallest unit
+    /// * `window_ledgers` - Width of the rolling window, in ledgers
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`
+    pub fn set_withdraw_limit(
+        env: Env,
+        caller: Address,
+        asset_id: u32,
+        limit: i128,
+        window_ledgers: u32,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        storage::extend_instance_ttl(&env);
+        storage::set_withdraw_limit(&env, asset_id, limit, window_ledgers);
+
+        Ok(())
+    }
+
+    /// Get the configured withdrawal rate cap for an asset.
+    ///
+    /// # Arguments
+    /// * `asset_id` - Which registered asset to query
+    ///
+    /// # Returns
+    /// The configured limit, or `None` if withdrawals of this asset are unbounded
+    pub fn get_withdraw_limit(env: Env, asset_id: u32) -> Option<WithdrawLimit> {
+        storage::extend_instance_ttl(&env);
+        storage::get_withdraw_limit(&env, asset_id)
+    }
+
+    /// Deploy new contract code, swapping this contract's wasm in place.
+    ///
+    /// Only a caller holding `RoleAdmin` may upgrade. After upgrading, call
+    /// `migrate` to run any one-time storage migrations the new code
+    /// requires; the two-step flow lets an operator swap code and verify
+    /// it loaded before mutating storage.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `new_wasm_hash` - Hash of the new contract wasm already uploaded
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`
+    pub fn upgrade(env: Env, caller: Address, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Run one-time storage migrations for the currently deployed code.
+    ///
+    /// Idempotent: if the stored schema version already matches
+    /// `CURRENT_VERSION`, this is a no-op, so it's safe to call after
+    /// every `upgrade` without knowing whether a migration is pending.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`
+    pub fn migrate(env: Env, caller: Address) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        let old_version = storage::get_version(&env);
+        if old_version >= CURRENT_VERSION {
+            return Ok(());
+        }
+
+        storage::set_version(&env, CURRENT_VERSION);
+        events::emit_upgraded(&env, old_version, CURRENT_VERSION);
+
+        Ok(())
+    }
+
+    /// Settle a trade between two users, deducting the configured maker/taker
+    /// fees from each side's receipts.
+    ///
+    /// Only a caller holding the `Settler` role can authorize settlements.
+    /// The backend matches orders off-chain and submits settlements on-chain.
     ///
     /// In a trade:
     /// - The seller gives `amount_sold` of `asset_sold` to the buyer
     /// - The buyer gives `amount_bought` of `asset_bought` to the seller
     ///
+    /// The seller's receipt of `amount_bought` is treated as the maker side
+    /// (resting liquidity) and charged `maker_fee_bps`; the buyer's receipt
+    /// of `amount_sold` is treated as the taker side (crossing liquidity)
+    /// and charged `taker_fee_bps`. Each fee is credited to that asset's
+    /// protocol fee balance, minus an optional share routed to `referrer`.
+    ///
     /// # Arguments
+    /// * `operator` - Must hold `Settler` and authorize the call
     /// * `nonce` - Execution nonce (must match current contract nonce)
     /// * `buyer` - Address receiving asset_sold, paying asset_bought
     /// * `seller` - Address receiving asset_bought, paying asset_sold
-    /// * `asset_sold` - The asset being sold (flows seller → buyer)
+    /// * `asset_sold` - The registered asset being sold (flows seller → buyer)
     /// * `amount_sold` - Amount of asset_sold being traded
-    /// * `asset_bought` - The asset being bought (flows buyer → seller)
+    /// * `asset_bought` - The registered asset being bought (flows buyer → seller)
     /// * `amount_bought` - Amount of asset_bought being traded
+    /// * `referrer` - Optional address to receive a share of the collected fees
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `operator` lacks `Settler`,
+    /// `Error::ContractPaused` if the contract is paused,
+    /// `Error::NonPositiveAmount` if either amount is not positive,
+    /// `Error::InsufficientBalance` if either party lacks funds, or
+    /// `Error::InvalidNonce` if the nonce doesn't match
     ///
     /// # Panics
-    /// Panics if amounts are not positive, either party has insufficient balance,
-    /// nonce doesn't match, or admin doesn't authorize
+    /// Panics if `operator` doesn't authorize
     pub fn settle(
         env: Env,
+        operator: Address,
         nonce: u64,
         buyer: Address,
         seller: Address,
-        asset_sold: Asset,
+        asset_sold: u32,
         amount_sold: i128,
-        asset_bought: Asset,
+        asset_bought: u32,
         amount_bought: i128,
-    ) {
-        // Admin must authorize settlements
-        let admin = storage::get_admin(&env);
-        admin.require_auth();
+        referrer: Option<Address>,
+    ) -> Result<(), Error> {
+        // Operator must hold the Settler role and authorize the call
+        operator.require_auth();
+        storage::require_role(&env, &operator, Role::Settler)?;
+        storage::require_not_paused(&env)?;
 
         if amount_sold <= 0 || amount_bought <= 0 {
-            panic!("Amounts must be positive");
+            return Err(Error::NonPositiveAmount);
         }
 
         storage::extend_instance_ttl(&env);
 
         // Validate nonce matches current value
-        storage::validate_nonce(&env, nonce);
+        storage::validate_nonce(&env, nonce)?;
+
+        let maker_fee = amount_bought * storage::get_maker_fee_bps(&env) as i128 / 10_000;
+        let taker_fee = amount_sold * storage::get_taker_fee_bps(&env) as i128 / 10_000;
 
         // Update seller's balances:
         // - Decrease asset_sold (what they're selling)
-        // - Increase asset_bought (what they're receiving as payment)
-        storage::decrease_balance(&env, &seller, asset_sold, amount_sold);
-        storage::increase_balance(&env, &seller, asset_bought, amount_bought);
+        // - Increase asset_bought (what they're receiving as payment), net of the maker fee
+        storage::decrease_balance(&env, &seller, asset_sold, amount_sold)?;
+        storage::increase_balance(&env, &seller, asset_bought, amount_bought - maker_fee);
 
         // Update buyer's balances:
-        // - Increase asset_sold (what they're buying)
+        // - Increase asset_sold (what they're buying), net of the taker fee
         // - Decrease asset_bought (what they're paying)
-        storage::increase_balance(&env, &buyer, asset_sold, amount_sold);
-        storage::decrease_balance(&env, &buyer, asset_bought, amount_bought);
+        storage::increase_balance(&env, &buyer, asset_sold, amount_sold - taker_fee);
+        storage::decrease_balance(&env, &buyer, asset_bought, amount_bought)?;
+
+        Self::collect_fee(&env, asset_bought, maker_fee, &referrer);
+        Self::collect_fee(&env, asset_sold, taker_fee, &referrer);
 
         // Increment nonce after successful execution
         storage::increment_nonce(&env);
@@ -178,40 +563,395 @@ impl OrderBookContract {
             asset_bought,
             amount_bought,
         );
+
+        Ok(())
+    }
+
+    /// Route a collected fee between the protocol's fee balance and an
+    /// optional referrer's share. A no-op if `fee` is zero.
+    fn collect_fee(env: &Env, asset_id: u32, fee: i128, referrer: &Option<Address>) {
+        if fee <= 0 {
+            return;
+        }
+
+        match referrer {
+            Some(referrer) => {
+                let referrer_share = fee * storage::get_referrer_share_bps(env) as i128 / 10_000;
+                if referrer_share > 0 {
+                    storage::increase_balance(env, referrer, asset_id, referrer_share);
+                }
+                storage::credit_fee_balance(env, asset_id, fee - referrer_share);
+            }
+            None => storage::credit_fee_balance(env, asset_id, fee),
+        }
+    }
+
+    /// Configure the maker- and taker-side fees deducted from each `settle`.
+    ///
+    /// Only a caller holding `RoleAdmin` may configure fees.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `maker_fee_bps` - Fee charged on the seller's receipt, in basis points
+    /// * `taker_fee_bps` - Fee charged on the buyer's receipt, in basis points
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`, or
+    /// `Error::InvalidBps` if either fee exceeds 10000 basis points (100%)
+    pub fn set_fee_bps(
+        env: Env,
+        caller: Address,
+        maker_fee_bps: u32,
+        taker_fee_bps: u32,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        if maker_fee_bps > 10_000 || taker_fee_bps > 10_000 {
+            return Err(Error::InvalidBps);
+        }
+
+        storage::extend_instance_ttl(&env);
+        storage::set_fee_bps(&env, maker_fee_bps, taker_fee_bps);
+
+        Ok(())
+    }
+
+    /// Configure the share of each collected fee routed to a trade's referrer.
+    ///
+    /// Only a caller holding `RoleAdmin` may configure the referrer share.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `referrer_share_bps` - Share of each collected fee paid to the referrer
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`, or
+    /// `Error::InvalidBps` if `referrer_share_bps` exceeds 10000 basis
+    /// points (100%)
+    pub fn set_referrer_share_bps(
+        env: Env,
+        caller: Address,
+        referrer_share_bps: u32,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        if referrer_share_bps > 10_000 {
+            return Err(Error::InvalidBps);
+        }
+
+        storage::extend_instance_ttl(&env);
+        storage::set_referrer_share_bps(&env, referrer_share_bps);
+
+        Ok(())
+    }
+
+    /// Get the accrued protocol fee balance for an asset.
+    ///
+    /// # Arguments
+    /// * `asset_id` - Which registered asset to query
+    ///
+    /// # Returns
+    /// The accrued fee balance, or 0 if none has accrued
+    pub fn get_fees(env: Env, asset_id: u32) -> i128 {
+        storage::extend_instance_ttl(&env);
+        storage::get_fee_balance(&env, asset_id)
+    }
+
+    /// Withdraw accrued protocol fees for an asset to the caller.
+    ///
+    /// Only a caller holding `RoleAdmin` may withdraw fees.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold `RoleAdmin` and authorize the call
+    /// * `asset_id` - Which registered asset's fees to withdraw
+    /// * `amount` - Amount to withdraw (must be positive)
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `caller` lacks `RoleAdmin`,
+    /// `Error::NonPositiveAmount` if amount is not positive,
+    /// `Error::InsufficientBalance` if `amount` exceeds the accrued balance,
+    /// or `Error::NotInitialized` if `asset_id` isn't registered
+    ///
+    /// # Panics
+    /// Panics if token transfer fails
+    pub fn withdraw_fees(
+        env: Env,
+        caller: Address,
+        asset_id: u32,
+        amount: i128,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        storage::require_role(&env, &caller, Role::RoleAdmin)?;
+
+        if amount <= 0 {
+            return Err(Error::NonPositiveAmount);
+        }
+
+        storage::extend_instance_ttl(&env);
+        let token_address = storage::get_asset_address(&env, asset_id)?;
+        storage::debit_fee_balance(&env, asset_id, amount)?;
+
+        let token_client = token::Client::new(&env, &token_address);
+        let contract_address = env.current_contract_address();
+        token_client.transfer(&contract_address, &caller, &amount);
+
+        events::emit_fees_withdrawn(&env, &caller, asset_id, amount);
+
+        Ok(())
+    }
+
+    /// Settle a batch of trades matched in the same round, indivisibly: a
+    /// contract invocation either commits every trade's balance update or,
+    /// if any trade in the list fails, none of them — Soroban reverts all
+    /// storage writes made during a failed invocation, so there's no
+    /// partial-batch state to clean up. This lets a dark-pool operator
+    /// clear a whole matching round in one invocation instead of paying
+    /// per-trade overhead for N separate `settle` calls.
+    ///
+    /// Same `Settler` authorization, fee schedule, and balance-update logic
+    /// as `settle` — applied per trade — but amortizes the role check and
+    /// the instance-TTL bump across the whole batch instead of paying them
+    /// once per trade. The nonce is validated once against `start_nonce`,
+    /// then incremented internally after each trade so every emitted
+    /// `settle` event still carries its own unique nonce. Unlike
+    /// `settle`, collected fees aren't split with a referrer.
+    ///
+    /// # Arguments
+    /// * `operator` - Must hold `Settler` and authorize the call
+    /// * `start_nonce` - Execution nonce expected before the first trade
+    /// * `trades` - The matched trades to apply, in order
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `operator` lacks `Settler`,
+    /// `Error::ContractPaused` if the contract is paused,
+    /// `Error::InvalidNonce` if `start_nonce` doesn't match the current
+    /// nonce, `Error::NonPositiveAmount` if any trade has non-positive
+    /// amounts, or `Error::InsufficientBalance` if either party in any trade
+    /// has insufficient balance. Any error reverts the whole batch.
+    ///
+    /// # Panics
+    /// Panics if `operator` doesn't authorize
+    pub fn settle_batch(
+        env: Env,
+        operator: Address,
+        start_nonce: u64,
+        trades: Vec<Trade>,
+    ) -> Result<(), Error> {
+        // Operator must hold the Settler role and authorize the call
+        operator.require_auth();
+        storage::require_role(&env, &operator, Role::Settler)?;
+        storage::require_not_paused(&env)?;
+
+        storage::extend_instance_ttl(&env);
+
+        // Validate the batch starts where the contract expects
+        storage::validate_nonce(&env, start_nonce)?;
+
+        for trade in trades.iter() {
+            if trade.amount_sold <= 0 || trade.amount_bought <= 0 {
+                return Err(Error::NonPositiveAmount);
+            }
+
+            let nonce = storage::get_nonce(&env);
+
+            // Same maker/taker fee schedule as `settle`, so routing a trade
+            // through a batch isn't a way to dodge fees.
+            let maker_fee = trade.amount_bought * storage::get_maker_fee_bps(&env) as i128 / 10_000;
+            let taker_fee = trade.amount_sold * storage::get_taker_fee_bps(&env) as i128 / 10_000;
+
+            // Update seller's balances:
+            // - Decrease asset_sold (what they're selling)
+            // - Increase asset_bought (what they're receiving as payment), net of the maker fee
+            storage::decrease_balance(&env, &trade.seller, trade.asset_sold, trade.amount_sold)?;
+            storage::increase_balance(&env, &trade.seller, trade.asset_bought, trade.amount_bought - maker_fee);
+
+            // Update buyer's balances:
+            // - Increase asset_sold (what they're buying), net of the taker fee
+            // - Decrease asset_bought (what they're paying)
+            storage::increase_balance(&env, &trade.buyer, trade.asset_sold, trade.amount_sold - taker_fee);
+            storage::decrease_balance(&env, &trade.buyer, trade.asset_bought, trade.amount_bought)?;
+
+            Self::collect_fee(&env, trade.asset_bought, maker_fee, &None);
+            Self::collect_fee(&env, trade.asset_sold, taker_fee, &None);
+
+            // Increment nonce after each trade so every event carries a unique nonce
+            storage::increment_nonce(&env);
+
+            events::emit_settle(
+                &env,
+                nonce,
+                &trade.buyer,
+                &trade.seller,
+                trade.asset_sold,
+                trade.amount_sold,
+                trade.asset_bought,
+                trade.amount_bought,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Settle a trade between two makers who each signed their own order,
+    /// so the submitting operator cannot pick amounts on a maker's behalf.
+    ///
+    /// `buy_order.maker.require_auth()` and `sell_order.maker.require_auth()`
+    /// authenticate over this entire invocation (including the nested order
+    /// arguments), so each maker is only ever bound to the exact order they
+    /// signed. The operator still submits the matched pair and still needs
+    /// `Settler`, but can no longer settle amounts neither maker agreed to.
+    ///
+    /// The two orders must cross: `sell_order` must sell `buy_order`'s
+    /// `asset_buy` in exchange for `buy_order`'s `asset_sell`, and each
+    /// maker receives the other's `amount_sell`, which must cover their own
+    /// `min_amount_buy` — each maker's full `amount_sell` is traded at once. The same maker/taker fee schedule
+    /// as `settle` applies (`sell_order`'s maker pays the maker fee,
+    /// `buy_order`'s maker pays the taker fee), and `min_amount_buy` is
+    /// enforced against what each maker actually receives net of fees —
+    /// unlike `settle`, there's no referrer split here.
+    ///
+    /// # Arguments
+    /// * `operator` - Must hold `Settler` and authorize the call
+    /// * `buy_order` - One maker's signed order
+    /// * `sell_order` - The other maker's signed, crossing order
+    ///
+    /// # Errors
+    /// Returns `Error::Unauthorized` if `operator` lacks `Settler`,
+    /// `Error::ContractPaused` if the contract is paused,
+    /// `Error::NonPositiveAmount` if either order's `amount_sell` or
+    /// `min_amount_buy` is not positive, `Error::OrderExpired` if either
+    /// order's `expiry_ledger` has passed, `Error::OrderMismatch` if the two
+    /// orders don't cross on the same asset pair, `Error::LimitNotMet` if
+    /// either maker would receive less than their `min_amount_buy` net of
+    /// fees, `Error::InvalidNonce` if either order's `nonce` doesn't match
+    /// that maker's current nonce, or `Error::InsufficientBalance` if either
+    /// maker lacks funds
+    ///
+    /// # Panics
+    /// Panics if `operator`, `buy_order.maker`, or `sell_order.maker` don't authorize
+    pub fn settle_signed(
+        env: Env,
+        operator: Address,
+        buy_order: SignedOrder,
+        sell_order: SignedOrder,
+    ) -> Result<(), Error> {
+        operator.require_auth();
+        storage::require_role(&env, &operator, Role::Settler)?;
+        storage::require_not_paused(&env)?;
+
+        buy_order.maker.require_auth();
+        sell_order.maker.require_auth();
+
+        if buy_order.amount_sell <= 0
+            || buy_order.min_amount_buy <= 0
+            || sell_order.amount_sell <= 0
+            || sell_order.min_amount_buy <= 0
+        {
+            return Err(Error::NonPositiveAmount);
+        }
+
+        storage::extend_instance_ttl(&env);
+
+        let current_ledger = env.ledger().sequence();
+        if current_ledger > buy_order.expiry_ledger || current_ledger > sell_order.expiry_ledger {
+            return Err(Error::OrderExpired);
+        }
+
+        if buy_order.asset_sell != sell_order.asset_buy || buy_order.asset_buy != sell_order.asset_sell {
+            return Err(Error::OrderMismatch);
+        }
+
+        // Same maker/taker fee schedule as `settle`: sell_order's maker
+        // receives buy_order's proceeds (the maker side), buy_order's maker
+        // receives sell_order's proceeds (the taker side). Each maker's
+        // `min_amount_buy` is checked against what they actually receive
+        // net of fees, not the pre-fee amount.
+        let maker_fee = buy_order.amount_sell * storage::get_maker_fee_bps(&env) as i128 / 10_000;
+        let taker_fee = sell_order.amount_sell * storage::get_taker_fee_bps(&env) as i128 / 10_000;
+
+        if sell_order.amount_sell - taker_fee < buy_order.min_amount_buy
+            || buy_order.amount_sell - maker_fee < sell_order.min_amount_buy
+        {
+            return Err(Error::LimitNotMet);
+        }
+
+        storage::consume_user_nonce(&env, &buy_order.maker, buy_order.nonce)?;
+        storage::consume_user_nonce(&env, &sell_order.maker, sell_order.nonce)?;
+
+        storage::decrease_balance(&env, &buy_order.maker, buy_order.asset_sell, buy_order.amount_sell)?;
+        storage::increase_balance(&env, &sell_order.maker, buy_order.asset_sell, buy_order.amount_sell - maker_fee);
+
+        storage::decrease_balance(&env, &sell_order.maker, sell_order.asset_sell, sell_order.amount_sell)?;
+        storage::increase_balance(&env, &buy_order.maker, sell_order.asset_sell, sell_order.amount_sell - taker_fee);
+
+        Self::collect_fee(&env, buy_order.asset_sell, maker_fee, &None);
+        Self::collect_fee(&env, sell_order.asset_sell, taker_fee, &None);
+
+        events::emit_signed_settle(
+            &env,
+            &buy_order.maker,
+            &sell_order.maker,
+            buy_order.asset_sell,
+            buy_order.amount_sell,
+            sell_order.asset_sell,
+            sell_order.amount_sell,
+        );
+
+        Ok(())
+    }
+
+    /// Get a user's current `settle_signed` replay-protection nonce.
+    ///
+    /// # Arguments
+    /// * `user` - The maker address to query
+    ///
+    /// # Returns
+    /// The nonce `user`'s next signed order must carry
+    pub fn get_user_nonce(env: Env, user: Address) -> u64 {
+        storage::extend_instance_ttl(&env);
+        storage::get_user_nonce(&env, &user)
     }
 
     /// Get a user's balance for a specific asset.
     ///
     /// # Arguments
     /// * `user` - The user to query
-    /// * `asset` - Which asset to query (A or B)
+    /// * `asset_id` - Which registered asset to query
     ///
     /// # Returns
     /// The user's balance, or 0 if they have no balance
-    pub fn get_balance(env: Env, user: Address, asset: Asset) -> i128 {
+    pub fn get_balance(env: Env, user: Address, asset_id: u32) -> i128 {
         storage::extend_instance_ttl(&env);
-        storage::get_user_balance(&env, &user, asset)
+        storage::get_user_balance(&env, &user, asset_id)
     }
 
     /// Get the admin address.
     ///
+    /// # Errors
+    /// Returns `Error::NotInitialized` if the admin isn't set
+    ///
     /// # Returns
     /// The admin address
-    pub fn get_admin(env: Env) -> Address {
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
         storage::extend_instance_ttl(&env);
         storage::get_admin(&env)
     }
 
-    /// Get the token contract address for an asset.
+    /// Get the token contract address registered under an asset id.
     ///
     /// # Arguments
-    /// * `asset` - Which asset to query (A or B)
+    /// * `asset_id` - Which registered asset to query
+    ///
+    /// # Errors
+    /// Returns `Error::NotInitialized` if `asset_id` isn't registered
     ///
     /// # Returns
     /// The token contract address
-    pub fn get_asset(env: Env, asset: Asset) -> Address {
+    pub fn get_asset(env: Env, asset_id: u32) -> Result<Address, Error> {
         storage::extend_instance_ttl(&env);
-        storage::get_asset_address(&env, asset)
+        storage::get_asset_address(&env, asset_id)
     }
 
     /// Get the current execution nonce.
@@ -225,6 +965,193 @@ impl OrderBookContract {
         storage::extend_instance_ttl(&env);
         storage::get_nonce(&env)
     }
+
+    /// Place a limit order on the on-chain book, matching immediately
+    /// against any crossing resting orders before any unfilled remainder
+    /// rests at `price`.
+    ///
+    /// Balance for the order's full size is locked up front: `amount` of
+    /// `base_asset` for an `Ask`, or `amount * price` of `quote_asset`
+    /// (rounded up) for a `Bid`. Matches execute at the resting maker's
+    /// price, never the taker's limit; if that gives the taker a better
+    /// price than they locked for, the difference is refunded immediately.
+    ///
+    /// # Arguments
+    /// * `user` - The order owner; must authorize the call
+    /// * `base_asset` - The registered asset being bought or sold
+    /// * `quote_asset` - The registered asset `price` is denominated in
+    /// * `side` - `Bid` to buy `base_asset`, `Ask` to sell it
+    /// * `price` - Limit price, in `quote_asset` units per `base_asset`
+    ///   unit, scaled by `storage::PRICE_SCALE`
+    /// * `amount` - Order size, in `base_asset` units (must be positive)
+    ///
+    /// # Errors
+    /// Returns `Error::ContractPaused` if the contract is paused,
+    /// `Error::NonPositiveAmount` if `amount` or `price` is not positive,
+    /// `Error::NotInitialized` if either asset isn't registered, or
+    /// `Error::InsufficientBalance` if `user` lacks funds to lock
+    ///
+    /// # Returns
+    /// The id of the resting order if any of `amount` remained unfilled,
+    /// or `None` if the order filled completely
+    pub fn place_order(
+        env: Env,
+        user: Address,
+        base_asset: u32,
+        quote_asset: u32,
+        side: Side,
+        price: i128,
+        amount: i128,
+    ) -> Result<Option<u64>, Error> {
+        user.require_auth();
+        storage::require_not_paused(&env)?;
+
+        if amount <= 0 || price <= 0 {
+            return Err(Error::NonPositiveAmount);
+        }
+
+        storage::get_asset_address(&env, base_asset)?;
+        storage::get_asset_address(&env, quote_asset)?;
+        storage::extend_instance_ttl(&env);
+
+        match side {
+            Side::Ask => storage::decrease_balance(&env, &user, base_asset, amount)?,
+            Side::Bid => {
+                let locked = ceil_div(amount * price, storage::PRICE_SCALE);
+                storage::decrease_balance(&env, &user, quote_asset, locked)?;
+            }
+        }
+
+        let opposite = match side {
+            Side::Bid => Side::Ask,
+            Side::Ask => Side::Bid,
+        };
+        let mut remaining = amount;
+
+        while remaining > 0 {
+            let best = storage::best_price(&env, base_asset, quote_asset, opposite);
+            let crosses = match (side, best) {
+                (Side::Bid, Some(ask_price)) => price >= ask_price,
+                (Side::Ask, Some(bid_price)) => price <= bid_price,
+                _ => false,
+            };
+            if !crosses {
+                break;
+            }
+            let fill_price = best.unwrap();
+
+            let mut maker =
+                match storage::pop_front_at_price(&env, base_asset, quote_asset, opposite, fill_price) {
+                    Some(maker) => maker,
+                    None => break,
+                };
+
+            let fill_qty = if remaining < maker.remaining {
+                remaining
+            } else {
+                maker.remaining
+            };
+
+            let (buyer, seller) = match side {
+                Side::Bid => (user.clone(), maker.owner.clone()),
+                Side::Ask => (maker.owner.clone(), user.clone()),
+            };
+
+            let quote_amount = (fill_qty * fill_price) / storage::PRICE_SCALE;
+
+            storage::increase_balance(&env, &buyer, base_asset, fill_qty);
+            storage::increase_balance(&env, &seller, quote_asset, quote_amount);
+
+            // If the taker is a bidder, refund any quote that was locked
+            // above the actual (maker) execution price.
+            if side == Side::Bid {
+                let taker_locked_for_fill = ceil_div(fill_qty * price, storage::PRICE_SCALE);
+                if taker_locked_for_fill > quote_amount {
+                    storage::increase_balance(
+                        &env,
+                        &buyer,
+                        quote_asset,
+                        taker_locked_for_fill - quote_amount,
+                    );
+                }
+            }
+
+            events::emit_fill(
+                &env,
+                maker.id,
+                &buyer,
+                &seller,
+                base_asset,
+                quote_asset,
+                fill_qty,
+                fill_price,
+            );
+
+            maker.remaining -= fill_qty;
+            remaining -= fill_qty;
+
+            if maker.remaining > 0 {
+                storage::requeue_front(&env, &maker);
+            }
+        }
+
+        if remaining > 0 {
+            let order_id = storage::next_order_id(&env);
+            let order = Order {
+                id: order_id,
+                owner: user,
+                base_asset,
+                quote_asset,
+                side,
+                price,
+                remaining,
+            };
+            storage::rest_order(&env, &order);
+            events::emit_order_placed(&env, order_id, &order.owner, side, price, remaining);
+            return Ok(Some(order_id));
+        }
+
+        Ok(None)
+    }
+
+    /// Cancel a resting order, refunding the owner's locked balance.
+    ///
+    /// # Arguments
+    /// * `user` - Must be the order's owner and authorize the call
+    /// * `order_id` - The resting order to cancel
+    ///
+    /// # Errors
+    /// Returns `Error::OrderNotFound` if no such order is resting, or
+    /// `Error::Unauthorized` if `user` isn't the order's owner
+    pub fn cancel_order(env: Env, user: Address, order_id: u64) -> Result<(), Error> {
+        user.require_auth();
+
+        let order = storage::get_order(&env, order_id)?;
+        if order.owner != user {
+            return Err(Error::Unauthorized);
+        }
+
+        storage::extend_instance_ttl(&env);
+
+        match order.side {
+            Side::Ask => storage::increase_balance(&env, &user, order.base_asset, order.remaining),
+            Side::Bid => {
+                let locked = ceil_div(order.remaining * order.price, storage::PRICE_SCALE);
+                storage::increase_balance(&env, &user, order.quote_asset, locked);
+            }
+        }
+
+        storage::remove_from_book(&env, &order);
+        events::emit_order_cancelled(&env, order_id, &user);
+
+        Ok(())
+    }
+}
+
+/// Round an integer division up instead of truncating, used when locking
+/// (rather than crediting) balances so the contract never under-collects.
+fn ceil_div(numerator: i128, denominator: i128) -> i128 {
+    (numerator + denominator - 1) / denominator
 }
 
 #[cfg(test)]
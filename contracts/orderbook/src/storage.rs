@@ -1,6 +1,6 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{Address, Env, Vec};
 
-use crate::types::{Asset, DataKey};
+use crate::types::{DataKey, Error, Order, Role, Side, WithdrawLimit, WithdrawWindow};
 
 // TTL constants for storage entries
 const DAY_IN_LEDGERS: u32 = 17280; // ~24 hours at 5s per ledger
@@ -8,6 +8,15 @@ const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
 const INSTANCE_LIFETIME_THRESHOLD: u32 = DAY_IN_LEDGERS;
 const BALANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
 const BALANCE_LIFETIME_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS;
+const ROLE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const ROLE_LIFETIME_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS;
+const ORDER_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const ORDER_LIFETIME_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS;
+
+/// Scale factor applied to `Order::price`: quote-asset units per
+/// base-asset unit, scaled by this constant (e.g. a price of
+/// `2 * PRICE_SCALE` means 2 quote units per 1 base unit).
+pub const PRICE_SCALE: i128 = 10_000_000;
 
 /// Extend the TTL of instance storage
 pub fn extend_instance_ttl(env: &Env) {
@@ -17,11 +26,11 @@ pub fn extend_instance_ttl(env: &Env) {
 }
 
 /// Get the admin address
-pub fn get_admin(env: &Env) -> Address {
+pub fn get_admin(env: &Env) -> Result<Address, Error> {
     env.storage()
         .instance()
         .get(&DataKey::Admin)
-        .expect("Admin not set")
+        .ok_or(Error::NotInitialized)
 }
 
 /// Set the admin address
@@ -29,38 +38,88 @@ pub fn set_admin(env: &Env, admin: &Address) {
     env.storage().instance().set(&DataKey::Admin, admin);
 }
 
-/// Get the token contract address for an asset
-pub fn get_asset_address(env: &Env, asset: Asset) -> Address {
-    let key = match asset {
-        Asset::A => DataKey::AssetA,
-        Asset::B => DataKey::AssetB,
-    };
+/// Get the number of assets registered so far; also the next id to assign
+/// Returns 0 if no assets have been registered yet
+pub fn get_asset_count(env: &Env) -> u32 {
     env.storage()
         .instance()
-        .get(&key)
-        .expect("Asset not set")
+        .get(&DataKey::AssetCount)
+        .unwrap_or(0)
 }
 
-/// Set the token contract address for asset A
-pub fn set_asset_a(env: &Env, address: &Address) {
-    env.storage().instance().set(&DataKey::AssetA, address);
+/// Check whether an asset id has been registered
+pub fn asset_exists(env: &Env, asset_id: u32) -> bool {
+    env.storage().instance().has(&DataKey::AssetToken(asset_id))
 }
 
-/// Set the token contract address for asset B
-pub fn set_asset_b(env: &Env, address: &Address) {
-    env.storage().instance().set(&DataKey::AssetB, address);
+/// Get the token contract address registered under an asset id
+/// Returns `Error::NotInitialized` if the id isn't registered
+pub fn get_asset_address(env: &Env, asset_id: u32) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::AssetToken(asset_id))
+        .ok_or(Error::NotInitialized)
+}
+
+/// Register a new token contract, assigning it the next available asset id
+/// Returns `Error::AssetAlreadyRegistered` if the token is already registered
+pub fn register_asset(env: &Env, token: &Address) -> Result<u32, Error> {
+    let token_key = DataKey::TokenAssetId(token.clone());
+    if env.storage().instance().has(&token_key) {
+        return Err(Error::AssetAlreadyRegistered);
+    }
+
+    let asset_id = get_asset_count(env);
+    env.storage()
+        .instance()
+        .set(&DataKey::AssetToken(asset_id), token);
+    env.storage().instance().set(&token_key, &asset_id);
+    env.storage()
+        .instance()
+        .set(&DataKey::AssetCount, &(asset_id + 1));
+
+    Ok(asset_id)
+}
+
+/// Set the decimals reported by a token's SAC metadata, captured at registration time
+pub fn set_asset_decimals(env: &Env, asset_id: u32, decimals: u32) {
+    env.storage()
+        .instance()
+        .set(&DataKey::AssetDecimals(asset_id), &decimals);
+}
+
+/// Get the decimals recorded for a registered asset
+/// Returns `Error::NotInitialized` if the asset isn't registered
+pub fn get_decimals(env: &Env, asset_id: u32) -> Result<u32, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::AssetDecimals(asset_id))
+        .ok_or(Error::NotInitialized)
 }
 
 /// Get a user's balance for a specific asset
 /// Returns 0 if the user has no balance entry
-pub fn get_user_balance(env: &Env, user: &Address, asset: Asset) -> i128 {
-    let key = DataKey::UserBalance(user.clone(), asset);
-    env.storage().persistent().get(&key).unwrap_or(0)
+pub fn get_user_balance(env: &Env, user: &Address, asset_id: u32) -> i128 {
+    let key = DataKey::UserBalance(user.clone(), asset_id);
+    let balance = env.storage().persistent().get(&key);
+
+    // Bump TTL on read too, so an account that's only ever read from (e.g.
+    // the losing side of a settle, or idle between deposit and withdraw)
+    // doesn't risk archival purely from lack of writes.
+    if balance.is_some() {
+        env.storage().persistent().extend_ttl(
+            &key,
+            BALANCE_LIFETIME_THRESHOLD,
+            BALANCE_BUMP_AMOUNT,
+        );
+    }
+
+    balance.unwrap_or(0)
 }
 
 /// Set a user's balance for a specific asset
-pub fn set_user_balance(env: &Env, user: &Address, asset: Asset, balance: i128) {
-    let key = DataKey::UserBalance(user.clone(), asset);
+pub fn set_user_balance(env: &Env, user: &Address, asset_id: u32, balance: i128) {
+    let key = DataKey::UserBalance(user.clone(), asset_id);
     env.storage().persistent().set(&key, &balance);
 
     // Extend TTL for the balance entry
@@ -72,19 +131,20 @@ pub fn set_user_balance(env: &Env, user: &Address, asset: Asset, balance: i128)
 }
 
 /// Increase a user's balance for a specific asset
-pub fn increase_balance(env: &Env, user: &Address, asset: Asset, amount: i128) {
-    let current = get_user_balance(env, user, asset);
-    set_user_balance(env, user, asset, current + amount);
+pub fn increase_balance(env: &Env, user: &Address, asset_id: u32, amount: i128) {
+    let current = get_user_balance(env, user, asset_id);
+    set_user_balance(env, user, asset_id, current + amount);
 }
 
 /// Decrease a user's balance for a specific asset
-/// Panics if the user doesn't have enough balance
-pub fn decrease_balance(env: &Env, user: &Address, asset: Asset, amount: i128) {
-    let current = get_user_balance(env, user, asset);
+/// Returns `Error::InsufficientBalance` if the user doesn't have enough balance
+pub fn decrease_balance(env: &Env, user: &Address, asset_id: u32, amount: i128) -> Result<(), Error> {
+    let current = get_user_balance(env, user, asset_id);
     if current < amount {
-        panic!("Insufficient balance");
+        return Err(Error::InsufficientBalance);
     }
-    set_user_balance(env, user, asset, current - amount);
+    set_user_balance(env, user, asset_id, current - amount);
+    Ok(())
 }
 
 /// Get the current nonce value
@@ -97,12 +157,13 @@ pub fn get_nonce(env: &Env) -> u64 {
 }
 
 /// Validate that the provided nonce matches the current nonce
-/// Panics if the nonce doesn't match
-pub fn validate_nonce(env: &Env, expected_nonce: u64) {
+/// Returns `Error::InvalidNonce` if the nonce doesn't match
+pub fn validate_nonce(env: &Env, expected_nonce: u64) -> Result<(), Error> {
     let current = get_nonce(env);
     if expected_nonce != current {
-        panic!("Invalid nonce: expected {}, got {}", current, expected_nonce);
+        return Err(Error::InvalidNonce);
     }
+    Ok(())
 }
 
 /// Increment the nonce by 1
@@ -112,3 +173,421 @@ pub fn increment_nonce(env: &Env) {
         .instance()
         .set(&DataKey::Nonce, &(current + 1));
 }
+
+/// Get the raw role bitmask granted to an address
+/// Returns 0 if the address has no roles
+fn get_role_mask(env: &Env, who: &Address) -> u32 {
+    let key = DataKey::Role(who.clone());
+    env.storage().persistent().get(&key).unwrap_or(0)
+}
+
+/// Set the raw role bitmask granted to an address
+fn set_role_mask(env: &Env, who: &Address, mask: u32) {
+    let key = DataKey::Role(who.clone());
+    env.storage().persistent().set(&key, &mask);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ROLE_LIFETIME_THRESHOLD, ROLE_BUMP_AMOUNT);
+}
+
+/// Check whether an address holds a given role
+pub fn has_role(env: &Env, who: &Address, role: Role) -> bool {
+    get_role_mask(env, who) & (role as u32) != 0
+}
+
+/// Grant a role to an address
+pub fn grant_role(env: &Env, who: &Address, role: Role) {
+    let mask = get_role_mask(env, who);
+    set_role_mask(env, who, mask | role as u32);
+}
+
+/// Revoke a role from an address
+pub fn revoke_role(env: &Env, who: &Address, role: Role) {
+    let mask = get_role_mask(env, who);
+    set_role_mask(env, who, mask & !(role as u32));
+}
+
+/// Require that an address holds a given role
+/// Returns `Error::Unauthorized` if it doesn't
+pub fn require_role(env: &Env, who: &Address, role: Role) -> Result<(), Error> {
+    if !has_role(env, who, role) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Check whether the contract is currently paused
+pub fn is_paused(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::Paused)
+        .unwrap_or(false)
+}
+
+/// Set the contract's paused flag
+pub fn set_paused(env: &Env, paused: bool) {
+    env.storage().instance().set(&DataKey::Paused, &paused);
+}
+
+/// Require that the contract is not paused
+/// Returns `Error::ContractPaused` if it is
+pub fn require_not_paused(env: &Env) -> Result<(), Error> {
+    if is_paused(env) {
+        return Err(Error::ContractPaused);
+    }
+    Ok(())
+}
+
+/// Get the current schema version
+/// Returns 0 if not yet initialized (pre-version-tracking deployments)
+pub fn get_version(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::Version).unwrap_or(0)
+}
+
+/// Set the schema version
+pub fn set_version(env: &Env, version: u32) {
+    env.storage().instance().set(&DataKey::Version, &version);
+}
+
+/// Get the configured withdrawal rate cap for an asset
+/// Returns `None` if no limit has been configured (withdrawals unbounded)
+pub fn get_withdraw_limit(env: &Env, asset_id: u32) -> Option<WithdrawLimit> {
+    env.storage().instance().get(&DataKey::WithdrawLimit(asset_id))
+}
+
+/// Set the withdrawal rate cap for an asset
+pub fn set_withdraw_limit(env: &Env, asset_id: u32, limit: i128, window_ledgers: u32) {
+    env.storage().instance().set(
+        &DataKey::WithdrawLimit(asset_id),
+        &WithdrawLimit { limit, window_ledgers },
+    );
+}
+
+/// Get the rolling-window accounting for an asset's withdrawal cap
+/// Returns a zeroed window starting at ledger 0 if none is recorded yet
+fn get_withdraw_window(env: &Env, asset_id: u32) -> WithdrawWindow {
+    env.storage()
+        .instance()
+        .get(&DataKey::WithdrawWindow(asset_id))
+        .unwrap_or(WithdrawWindow {
+            window_start_ledger: 0,
+            amount_withdrawn: 0,
+        })
+}
+
+/// Set the rolling-window accounting for an asset's withdrawal cap
+fn set_withdraw_window(env: &Env, asset_id: u32, window: &WithdrawWindow) {
+    env.storage()
+        .instance()
+        .set(&DataKey::WithdrawWindow(asset_id), window);
+}
+
+/// Check a withdrawal against the asset's configured rate limit and, if it
+/// fits, record it against the rolling window.
+///
+/// Rolls the window forward (resetting the accumulator) once the current
+/// ledger has advanced past `window_start_ledger + window_ledgers`. A no-op
+/// that always succeeds if no limit is configured for `asset_id`.
+///
+/// Returns `Error::WithdrawLimitExceeded` if `amount` would push the
+/// window's accumulated total past the configured limit.
+pub fn check_and_record_withdrawal(env: &Env, asset_id: u32, amount: i128) -> Result<(), Error> {
+    let limit_cfg = match get_withdraw_limit(env, asset_id) {
+        Some(limit_cfg) => limit_cfg,
+        None => return Ok(()),
+    };
+
+    let current_ledger = env.ledger().sequence();
+    let mut window = get_withdraw_window(env, asset_id);
+
+    if current_ledger >= window.window_start_ledger + limit_cfg.window_ledgers {
+        window.window_start_ledger = current_ledger;
+        window.amount_withdrawn = 0;
+    }
+
+    if window.amount_withdrawn + amount > limit_cfg.limit {
+        return Err(Error::WithdrawLimitExceeded);
+    }
+
+    window.amount_withdrawn += amount;
+    set_withdraw_window(env, asset_id, &window);
+
+    Ok(())
+}
+
+/// Assign the next order id, also used as the resting order's time-priority ordinal
+pub fn next_order_id(env: &Env) -> u64 {
+    let id = env.storage().instance().get(&DataKey::NextOrderId).unwrap_or(0);
+    env.storage().instance().set(&DataKey::NextOrderId, &(id + 1));
+    id
+}
+
+/// Get a resting order by id
+/// Returns `Error::OrderNotFound` if no such order is resting
+pub fn get_order(env: &Env, order_id: u64) -> Result<Order, Error> {
+    let key = DataKey::Order(order_id);
+    let order = env.storage().persistent().get(&key);
+
+    // Bump TTL on read too, so an order that's only ever read during
+    // matching (without being re-rested or removed) doesn't risk archival
+    // purely from lack of writes.
+    if order.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+    }
+
+    order.ok_or(Error::OrderNotFound)
+}
+
+fn set_order(env: &Env, order: &Order) {
+    let key = DataKey::Order(order.id);
+    env.storage().persistent().set(&key, order);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+}
+
+/// Get the sorted ascending active price levels on one side of a pair's book
+///
+/// Lives in persistent storage, like `Order` and `UserBalance`, rather than
+/// the shared instance entry: a permissionless `place_order` at an arbitrary
+/// price would otherwise let anyone grow the single instance blob loaded on
+/// every contract call.
+fn get_levels(env: &Env, base_asset: u32, quote_asset: u32, side: Side) -> Vec<i128> {
+    let key = DataKey::OrderBookLevels(base_asset, quote_asset, side);
+    let levels = env.storage().persistent().get(&key);
+
+    if levels.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+    }
+
+    levels.unwrap_or(Vec::new(env))
+}
+
+fn set_levels(env: &Env, base_asset: u32, quote_asset: u32, side: Side, levels: &Vec<i128>) {
+    let key = DataKey::OrderBookLevels(base_asset, quote_asset, side);
+    env.storage().persistent().set(&key, levels);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+}
+
+/// Get the FIFO queue of order ids resting at a price level
+///
+/// Also persistent storage, for the same reason as `get_levels`: each
+/// distinct resting price gets its own entry with its own TTL instead of
+/// permanently inflating the shared instance entry.
+fn get_queue(env: &Env, base_asset: u32, quote_asset: u32, side: Side, price: i128) -> Vec<u64> {
+    let key = DataKey::OrderBookQueue(base_asset, quote_asset, side, price);
+    let queue = env.storage().persistent().get(&key);
+
+    if queue.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+    }
+
+    queue.unwrap_or(Vec::new(env))
+}
+
+/// Persist a price level's queue, dropping the level entirely once it's empty
+fn set_queue(env: &Env, base_asset: u32, quote_asset: u32, side: Side, price: i128, queue: &Vec<u64>) {
+    let key = DataKey::OrderBookQueue(base_asset, quote_asset, side, price);
+    if queue.is_empty() {
+        env.storage().persistent().remove(&key);
+
+        let mut levels = get_levels(env, base_asset, quote_asset, side);
+        if let Some(idx) = levels.iter().position(|p| p == price) {
+            levels.remove(idx as u32);
+            set_levels(env, base_asset, quote_asset, side, &levels);
+        }
+    } else {
+        env.storage().persistent().set(&key, queue);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ORDER_LIFETIME_THRESHOLD, ORDER_BUMP_AMOUNT);
+    }
+}
+
+/// Peek the best resting price on one side of a pair's book: the lowest ask
+/// or the highest bid. Returns `None` if that side is empty.
+pub fn best_price(env: &Env, base_asset: u32, quote_asset: u32, side: Side) -> Option<i128> {
+    let levels = get_levels(env, base_asset, quote_asset, side);
+    match side {
+        Side::Ask => levels.first(),
+        Side::Bid => levels.last(),
+    }
+}
+
+/// Rest a freshly placed (or partially filled) order at the back of its
+/// price level's FIFO queue, inserting a new sorted price level if this is
+/// the first order resting at that price.
+pub fn rest_order(env: &Env, order: &Order) {
+    let mut levels = get_levels(env, order.base_asset, order.quote_asset, order.side);
+    if !levels.iter().any(|p| p == order.price) {
+        let idx = levels
+            .iter()
+            .position(|p| p > order.price)
+            .unwrap_or(levels.len() as usize);
+        levels.insert(idx as u32, order.price);
+        set_levels(env, order.base_asset, order.quote_asset, order.side, &levels);
+    }
+
+    let mut queue = get_queue(env, order.base_asset, order.quote_asset, order.side, order.price);
+    queue.push_back(order.id);
+    set_queue(env, order.base_asset, order.quote_asset, order.side, order.price, &queue);
+
+    set_order(env, order);
+}
+
+/// Requeue a partially-filled maker order at the *front* of its price
+/// level's queue, preserving its original time priority
+pub fn requeue_front(env: &Env, order: &Order) {
+    // `pop_front_at_price` drops the level from the sorted index once its
+    // queue empties, so restore it before requeueing.
+    let mut levels = get_levels(env, order.base_asset, order.quote_asset, order.side);
+    if !levels.iter().any(|p| p == order.price) {
+        let idx = levels
+            .iter()
+            .position(|p| p > order.price)
+            .unwrap_or(levels.len() as usize);
+        levels.insert(idx as u32, order.price);
+        set_levels(env, order.base_asset, order.quote_asset, order.side, &levels);
+    }
+
+    let mut queue = get_queue(env, order.base_asset, order.quote_asset, order.side, order.price);
+    queue.push_front(order.id);
+    set_queue(env, order.base_asset, order.quote_asset, order.side, order.price, &queue);
+    set_order(env, order);
+}
+
+/// Pop the order resting at the front of a price level's FIFO queue
+/// Returns `None` if the level has no resting orders
+pub fn pop_front_at_price(
+    env: &Env,
+    base_asset: u32,
+    quote_asset: u32,
+    side: Side,
+    price: i128,
+) -> Option<Order> {
+    let mut queue = get_queue(env, base_asset, quote_asset, side, price);
+    let order_id = queue.pop_front()?;
+    set_queue(env, base_asset, quote_asset, side, price, &queue);
+
+    let order = get_order(env, order_id).ok()?;
+    env.storage().persistent().remove(&DataKey::Order(order_id));
+    Some(order)
+}
+
+/// Remove a specific resting order from its price level (used by `cancel_order`)
+pub fn remove_from_book(env: &Env, order: &Order) {
+    let mut queue = get_queue(env, order.base_asset, order.quote_asset, order.side, order.price);
+    if let Some(idx) = queue.iter().position(|id| id == order.id) {
+        queue.remove(idx as u32);
+    }
+    set_queue(env, order.base_asset, order.quote_asset, order.side, order.price, &queue);
+    env.storage().persistent().remove(&DataKey::Order(order.id));
+}
+
+/// Get the configured maker-side fee for `settle`, in basis points
+/// Returns 0 if no fee has been configured
+pub fn get_maker_fee_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::MakerFeeBps).unwrap_or(0)
+}
+
+/// Get the configured taker-side fee for `settle`, in basis points
+/// Returns 0 if no fee has been configured
+pub fn get_taker_fee_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::TakerFeeBps).unwrap_or(0)
+}
+
+/// Set the maker- and taker-side fees deducted from each `settle`, in basis points
+pub fn set_fee_bps(env: &Env, maker_fee_bps: u32, taker_fee_bps: u32) {
+    env.storage().instance().set(&DataKey::MakerFeeBps, &maker_fee_bps);
+    env.storage().instance().set(&DataKey::TakerFeeBps, &taker_fee_bps);
+}
+
+/// Get the configured referrer share of each collected fee, in basis points
+/// Returns 0 if no share has been configured
+pub fn get_referrer_share_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::ReferrerShareBps)
+        .unwrap_or(0)
+}
+
+/// Set the referrer share of each collected fee, in basis points
+pub fn set_referrer_share_bps(env: &Env, referrer_share_bps: u32) {
+    env.storage()
+        .instance()
+        .set(&DataKey::ReferrerShareBps, &referrer_share_bps);
+}
+
+/// Get the accrued protocol fee balance for an asset
+/// Returns 0 if none has accrued
+pub fn get_fee_balance(env: &Env, asset_id: u32) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::FeeBalance(asset_id))
+        .unwrap_or(0)
+}
+
+/// Credit an asset's accrued protocol fee balance
+pub fn credit_fee_balance(env: &Env, asset_id: u32, amount: i128) {
+    let current = get_fee_balance(env, asset_id);
+    env.storage()
+        .instance()
+        .set(&DataKey::FeeBalance(asset_id), &(current + amount));
+}
+
+/// Debit an asset's accrued protocol fee balance
+/// Returns `Error::InsufficientBalance` if `amount` exceeds the accrued balance
+pub fn debit_fee_balance(env: &Env, asset_id: u32, amount: i128) -> Result<(), Error> {
+    let current = get_fee_balance(env, asset_id);
+    if current < amount {
+        return Err(Error::InsufficientBalance);
+    }
+    env.storage()
+        .instance()
+        .set(&DataKey::FeeBalance(asset_id), &(current - amount));
+    Ok(())
+}
+
+/// Get a user's current `settle_signed` replay-protection nonce
+/// Returns 0 if the user has never had a signed order settled
+pub fn get_user_nonce(env: &Env, user: &Address) -> u64 {
+    let key = DataKey::UserNonce(user.clone());
+    let nonce = env.storage().persistent().get(&key);
+
+    // Bump TTL on read too, same as `get_user_balance`, so reads alone
+    // (e.g. `get_user_nonce` queries between settlements) keep the entry
+    // from archival.
+    if nonce.is_some() {
+        env.storage().persistent().extend_ttl(
+            &key,
+            BALANCE_LIFETIME_THRESHOLD,
+            BALANCE_BUMP_AMOUNT,
+        );
+    }
+
+    nonce.unwrap_or(0)
+}
+
+/// Validate and consume a user's `settle_signed` nonce, bumping it so the
+/// same signed order can't be replayed
+/// Returns `Error::InvalidNonce` if `nonce` doesn't match the user's current nonce
+pub fn consume_user_nonce(env: &Env, user: &Address, nonce: u64) -> Result<(), Error> {
+    let current = get_user_nonce(env, user);
+    if nonce != current {
+        return Err(Error::InvalidNonce);
+    }
+    let key = DataKey::UserNonce(user.clone());
+    env.storage().persistent().set(&key, &(current + 1));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    Ok(())
+}
@@ -1,4 +1,122 @@
-use soroban_sdk::{contracttype, Address};
+use soroban_sdk::{contracterror, contracttype, Address};
+
+/// Errors returned by `OrderBookContract` entrypoints.
+///
+/// Stable, typed error codes let the backend and indexers branch on the
+/// failure reason instead of parsing panic message strings.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    InsufficientBalance = 1,
+    InvalidNonce = 2,
+    NonPositiveAmount = 3,
+    NotInitialized = 4,
+    Unauthorized = 5,
+    ContractPaused = 6,
+    AssetAlreadyRegistered = 7,
+    WithdrawLimitExceeded = 8,
+    OrderNotFound = 9,
+    OrderExpired = 10,
+    OrderMismatch = 11,
+    LimitNotMet = 12,
+    InvalidBps = 13,
+}
+
+/// An operator permission that can be granted to an address.
+///
+/// Roles are stored as bits in a per-address bitmask, so a single address
+/// (e.g. the bootstrap admin) can hold more than one role at a time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[contracttype]
+#[repr(u32)]
+pub enum Role {
+    /// May call `settle` / `settle_batch`
+    Settler = 1,
+    /// May call `withdraw`
+    Withdrawer = 2,
+    /// May grant/revoke roles
+    RoleAdmin = 4,
+}
+
+/// A single matched trade to be applied as part of a batch settlement.
+///
+/// Mirrors the arguments of `OrderBookContract::settle`, but without the
+/// nonce: batch nonces are derived from `start_nonce` plus the trade's
+/// position in the batch.
+#[derive(Clone)]
+#[contracttype]
+pub struct Trade {
+    pub buyer: Address,
+    pub seller: Address,
+    pub asset_sold: u32,
+    pub amount_sold: i128,
+    pub asset_bought: u32,
+    pub amount_bought: i128,
+}
+
+/// A configured rate cap on withdrawals of a given asset, denominated in
+/// the asset's own smallest unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct WithdrawLimit {
+    pub limit: i128,
+    pub window_ledgers: u32,
+}
+
+/// Rolling-window accounting for a `WithdrawLimit`.
+#[derive(Clone)]
+#[contracttype]
+pub struct WithdrawWindow {
+    pub window_start_ledger: u32,
+    pub amount_withdrawn: i128,
+}
+
+/// Which side of the book a resting or incoming order is on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[contracttype]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// A resting limit order in the on-chain book.
+///
+/// `price` is quote-asset units per base-asset unit, scaled by
+/// `storage::PRICE_SCALE`. `remaining` tracks how much of the original
+/// `amount` (in base-asset units) is still unfilled.
+#[derive(Clone)]
+#[contracttype]
+pub struct Order {
+    pub id: u64,
+    pub owner: Address,
+    pub base_asset: u32,
+    pub quote_asset: u32,
+    pub side: Side,
+    pub price: i128,
+    pub remaining: i128,
+}
+
+/// A user-signed order authorizing a specific trade, rather than trusting
+/// an operator to pick amounts on the maker's behalf.
+///
+/// Authorized by `maker.require_auth()` over the whole `settle_signed`
+/// invocation (of which this order is a nested argument), so the operator
+/// that submits it can only settle exactly what `maker` signed. `nonce`
+/// must match `maker`'s current per-user nonce, and is consumed (bumped)
+/// on a successful settlement, bounding replay to a single use; the order
+/// is also rejected once the current ledger passes `expiry_ledger`.
+#[derive(Clone)]
+#[contracttype]
+pub struct SignedOrder {
+    pub maker: Address,
+    pub asset_sell: u32,
+    pub amount_sell: i128,
+    pub asset_buy: u32,
+    pub min_amount_buy: i128,
+    pub expiry_ledger: u32,
+    pub nonce: u64,
+}
 
 /// Storage keys for the contract
 #[derive(Clone)]
@@ -6,20 +124,44 @@ use soroban_sdk::{contracttype, Address};
 pub enum DataKey {
     /// The admin address
     Admin,
-    /// Token contract address for asset A
-    AssetA,
-    /// Token contract address for asset B
-    AssetB,
-    /// User's balance for a specific asset: UserBalance(user_address, asset)
-    UserBalance(Address, Asset),
+    /// Token contract address registered under an asset id: AssetToken(id)
+    AssetToken(u32),
+    /// Reverse lookup used to reject double-registering a token: TokenAssetId(token_address)
+    TokenAssetId(Address),
+    /// Number of assets registered so far; also the next id to assign
+    AssetCount,
+    /// Decimals reported by the token's SAC metadata at registration time: AssetDecimals(asset_id)
+    AssetDecimals(u32),
+    /// User's balance for a specific asset id: UserBalance(user_address, asset_id)
+    UserBalance(Address, u32),
     /// Execution nonce for ensuring sequential execution order
     Nonce,
-}
-
-/// Represents which asset we're referring to
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-#[contracttype]
-pub enum Asset {
-    A,
-    B,
+    /// Bitmask of roles granted to an address: Role(address)
+    Role(Address),
+    /// Whether the contract is currently paused
+    Paused,
+    /// Schema version, bumped by `migrate` after an `upgrade`
+    Version,
+    /// Configured withdrawal rate cap for an asset: WithdrawLimit(asset_id)
+    WithdrawLimit(u32),
+    /// Rolling-window accounting for an asset's withdrawal cap: WithdrawWindow(asset_id)
+    WithdrawWindow(u32),
+    /// Next order id to assign; also doubles as the time-priority ordinal
+    NextOrderId,
+    /// A resting order by id: Order(order_id)
+    Order(u64),
+    /// Sorted ascending price levels with resting orders: OrderBookLevels(base_asset, quote_asset, side)
+    OrderBookLevels(u32, u32, Side),
+    /// FIFO queue of order ids resting at a price level: OrderBookQueue(base_asset, quote_asset, side, price)
+    OrderBookQueue(u32, u32, Side, i128),
+    /// Fee (in basis points) deducted from the maker side of a `settle`
+    MakerFeeBps,
+    /// Fee (in basis points) deducted from the taker side of a `settle`
+    TakerFeeBps,
+    /// Share (in basis points) of each fee routed to a trade's referrer, if any
+    ReferrerShareBps,
+    /// Accrued protocol fee balance for an asset, withdrawable by `RoleAdmin`: FeeBalance(asset_id)
+    FeeBalance(u32),
+    /// Per-user replay-protection nonce for `settle_signed`: UserNonce(user_address)
+    UserNonce(Address),
 }